@@ -16,6 +16,13 @@ impl Delay {
 			SendWrapper::new(TimeoutFuture::new(dur.as_millis() as u32))
 		)
 	}
+
+	/// Resets this timeout to a new timeout which will fire `dur` into the
+	/// future.
+	#[inline]
+	pub fn reset(&mut self, dur: Duration) {
+		self.0 = SendWrapper::new(TimeoutFuture::new(dur.as_millis() as u32));
+	}
 }
 
 impl Future for Delay {
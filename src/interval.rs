@@ -0,0 +1,123 @@
+//! A stream that yields repeatedly on a fixed period.
+
+use crate::Delay;
+use async_std::time::Instant;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use futures::Stream;
+
+/// The clock source backing scheduling, so an `Interval` paired with the
+/// native driver stays pausable/advanceable under the `mock-clock` feature and
+/// never drifts from the `Delay` it drives.
+#[inline]
+fn now() -> Instant {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        crate::native::now()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Instant::now()
+    }
+}
+
+/// Defines how an [`Interval`] behaves when ticks are missed because the
+/// consumer polled too slowly to keep up with the period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedTickBehavior {
+    /// Fire the queued ticks back-to-back until the interval has caught up.
+    Burst,
+    /// Skip the missed ticks and schedule the next tick `period` from now.
+    Delay,
+    /// Skip the missed ticks and realign to the next period boundary.
+    Skip,
+}
+
+impl Default for MissedTickBehavior {
+    fn default() -> Self {
+        MissedTickBehavior::Burst
+    }
+}
+
+/// A stream representing notifications at a fixed interval.
+///
+/// This is created through the `Interval::new` method indicating the period at
+/// which the stream should fire. Ticks are scheduled relative to the previous
+/// deadline rather than the time it was observed, so a slowly-polled interval
+/// does not drift later and later.
+#[derive(Debug)]
+pub struct Interval {
+    delay: Delay,
+    period: Duration,
+    /// Deadline of the tick currently being awaited.
+    deadline: Instant,
+    missed: MissedTickBehavior,
+}
+
+impl Interval {
+    /// Creates a new `Interval` which will fire every `period`.
+    ///
+    /// The first tick fires one `period` after the interval is created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    #[inline]
+    pub fn new(period: Duration) -> Self {
+        assert!(
+            !period.is_zero(),
+            "`Interval` period must be non-zero"
+        );
+        Self {
+            delay: Delay::new(period),
+            period,
+            deadline: now() + period,
+            missed: MissedTickBehavior::default(),
+        }
+    }
+
+    /// Returns how this interval handles missed ticks.
+    #[inline]
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed
+    }
+
+    /// Sets how this interval handles missed ticks.
+    #[inline]
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed = behavior;
+    }
+
+    /// Computes the deadline of the tick following `self.deadline`.
+    fn next_deadline(&self, now: Instant) -> Instant {
+        match self.missed {
+            MissedTickBehavior::Burst => self.deadline + self.period,
+            MissedTickBehavior::Delay => now + self.period,
+            MissedTickBehavior::Skip => {
+                let elapsed = now.saturating_duration_since(self.deadline);
+                let skipped = (elapsed.as_nanos() / self.period.as_nanos()) as u32;
+                self.deadline + self.period * (skipped + 1)
+            }
+        }
+    }
+}
+
+impl Stream for Interval {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.delay).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(()) => {
+                let now = now();
+                this.deadline = this.next_deadline(now);
+                this.delay
+                    .reset(this.deadline.saturating_duration_since(now));
+                Poll::Ready(Some(()))
+            }
+        }
+    }
+}
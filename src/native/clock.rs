@@ -0,0 +1,98 @@
+//! The clock source consulted by the driver and `Delay`.
+//!
+//! Production builds read the real monotonic clock with zero overhead. When the
+//! `mock-clock` feature is enabled a process-wide virtual clock can be frozen
+//! with [`pause`] and jumped forward with [`advance`], so time-dependent code
+//! can be driven deterministically in tests.
+
+extern crate std;
+
+use async_std::time::Instant;
+use core::time::Duration;
+
+#[cfg(not(feature = "mock-clock"))]
+pub(crate) use self::real::*;
+#[cfg(feature = "mock-clock")]
+pub use self::mock::*;
+
+#[cfg(not(feature = "mock-clock"))]
+mod real {
+    use super::*;
+
+    /// The current instant according to the real monotonic clock.
+    #[inline]
+    pub(crate) fn now() -> Instant {
+        Instant::now()
+    }
+
+    /// How long the driver should actually park to reach `target`.
+    #[inline]
+    pub(crate) fn park_until(target: Instant) -> Option<Duration> {
+        Some(target.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[cfg(feature = "mock-clock")]
+mod mock {
+    use super::*;
+    use std::sync::{Mutex, OnceLock};
+
+    struct State {
+        /// `Some` while the clock is frozen, holding the current virtual time.
+        frozen: Option<Instant>,
+    }
+
+    fn state() -> &'static Mutex<State> {
+        static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+        STATE.get_or_init(|| Mutex::new(State { frozen: None }))
+    }
+
+    /// The current instant, virtual while paused and real otherwise.
+    pub(crate) fn now() -> Instant {
+        match state().lock().unwrap().frozen {
+            Some(at) => at,
+            None => Instant::now(),
+        }
+    }
+
+    /// How long the driver should actually park to reach `target`. While the
+    /// clock is paused it returns `None`, meaning "park until woken", since real
+    /// time no longer moves virtual time forward.
+    pub(crate) fn park_until(target: Instant) -> Option<Duration> {
+        match state().lock().unwrap().frozen {
+            Some(_) => None,
+            None => Some(target.saturating_duration_since(Instant::now())),
+        }
+    }
+
+    /// Freezes the clock at the current instant.
+    pub fn pause() {
+        let mut state = state().lock().unwrap();
+        if state.frozen.is_none() {
+            state.frozen = Some(Instant::now());
+        }
+    }
+
+    /// Resumes the real monotonic clock.
+    pub fn resume() {
+        state().lock().unwrap().frozen = None;
+    }
+
+    /// Moves virtual time forward by `dur`, firing exactly the timers whose
+    /// deadlines are crossed. No-op unless the clock is paused.
+    pub fn advance(dur: Duration) {
+        {
+            let mut state = state().lock().unwrap();
+            match &mut state.frozen {
+                Some(at) => *at += dur,
+                None => return,
+            }
+        }
+        // Nudge the driver so it re-evaluates deadlines against virtual time.
+        // Hold the driver lock across the notify so the wakeup can't slip
+        // through the window between its `poll` and its `wait`.
+        let driver = super::super::driver();
+        let _guard = driver.inner.lock().unwrap();
+        driver.wakeup.notify_one();
+    }
+}
@@ -0,0 +1,311 @@
+//! A queue of keyed values that expire in deadline order.
+
+use super::heap::{Heap, Slot};
+use crate::Delay;
+use async_std::time::Instant;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use futures::Stream;
+use std::vec::Vec;
+
+/// A token identifying a value stored in a [`DelayQueue`].
+///
+/// Returned by [`DelayQueue::insert`] and accepted by
+/// [`DelayQueue::remove`]/[`DelayQueue::reset`]. The generation guards against
+/// a key being used after its slab slot has been reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    index: usize,
+    generation: u32,
+}
+
+/// The heap is ordered purely by deadline; `key` points back into the slab.
+#[derive(Debug)]
+struct HeapEntry {
+    at: Instant,
+    key: usize,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.at.cmp(&other.at)
+    }
+}
+
+#[derive(Debug)]
+enum SlabSlot<T> {
+    Empty { generation: u32 },
+    Full { generation: u32, value: T, slot: Slot },
+}
+
+/// Default number of expired values yielded in a single poll pass before the
+/// queue re-arms itself, so a thundering herd of simultaneous deadlines can't
+/// starve other tasks on the executor.
+const DEFAULT_BATCH_LIMIT: usize = 10;
+
+/// A queue that yields each inserted value once its delay has elapsed, in
+/// deadline order.
+///
+/// This lets callers manage many timers — connection idle timeouts, say —
+/// behind a single task rather than spawning one task per timer. Values are
+/// held in a slab keyed by [`Key`] and their deadlines in a [`Heap`], so
+/// cancelling or rescheduling an entry is `O(log n)`.
+#[derive(Debug)]
+pub struct DelayQueue<T> {
+    slab: Vec<SlabSlot<T>>,
+    free: Vec<usize>,
+    heap: Heap<HeapEntry>,
+    delay: Option<Delay>,
+    waker: Option<Waker>,
+    batch_limit: usize,
+    yielded: usize,
+}
+
+impl<T> DelayQueue<T> {
+    /// Creates an empty `DelayQueue`.
+    pub fn new() -> DelayQueue<T> {
+        DelayQueue {
+            slab: Vec::new(),
+            free: Vec::new(),
+            heap: Heap::new(),
+            delay: None,
+            waker: None,
+            batch_limit: DEFAULT_BATCH_LIMIT,
+            yielded: 0,
+        }
+    }
+
+    /// Sets how many expired values the queue yields in a single poll pass
+    /// before re-arming and returning control to the executor.
+    pub fn set_batch_limit(&mut self, limit: usize) {
+        self.batch_limit = limit.max(1);
+    }
+
+    /// Inserts `value`, returning a key that yields it after `dur` has elapsed.
+    pub fn insert(&mut self, value: T, dur: Duration) -> Key {
+        let at = super::clock::now() + dur;
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.slab.push(SlabSlot::Empty { generation: 0 });
+            self.slab.len() - 1
+        });
+        let generation = match self.slab[index] {
+            SlabSlot::Empty { generation } => generation,
+            SlabSlot::Full { .. } => unreachable!("free slot was full"),
+        };
+        let slot = self.heap.push(HeapEntry { at, key: index });
+        self.slab[index] = SlabSlot::Full {
+            generation,
+            value,
+            slot,
+        };
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+        Key { index, generation }
+    }
+
+    /// Removes the value associated with `key`, returning it if it had not yet
+    /// expired.
+    pub fn remove(&mut self, key: &Key) -> Option<T> {
+        match self.take(key) {
+            Some((value, slot)) => {
+                self.heap.remove(slot);
+                Some(value)
+            }
+            None => None,
+        }
+    }
+
+    /// Reschedules `key` to expire `dur` from now, returning `false` if the key
+    /// is no longer valid.
+    pub fn reset(&mut self, key: &Key, dur: Duration) -> bool {
+        let at = super::clock::now() + dur;
+        // Overwrite the slab slot in place rather than routing through `take`:
+        // the index must stay out of `self.free` and keep its generation, or the
+        // next `insert` would pop a "free" index that is actually `Full`.
+        let old_slot = match self.slab.get(key.index) {
+            Some(SlabSlot::Full { generation, slot, .. }) if *generation == key.generation => *slot,
+            _ => return false,
+        };
+        self.heap.remove(old_slot);
+        let slot = self.heap.push(HeapEntry {
+            at,
+            key: key.index,
+        });
+        match &mut self.slab[key.index] {
+            SlabSlot::Full {
+                slot: stored_slot, ..
+            } => *stored_slot = slot,
+            SlabSlot::Empty { .. } => unreachable!("checked Full above"),
+        }
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+
+    /// Removes the slab entry for `key`, handing back its value and heap slot,
+    /// and marks the slot free for reuse with a bumped generation.
+    fn take(&mut self, key: &Key) -> Option<(T, Slot)> {
+        let matches = matches!(
+            self.slab.get(key.index),
+            Some(SlabSlot::Full { generation, .. }) if *generation == key.generation
+        );
+        if !matches {
+            return None;
+        }
+        let replacement = SlabSlot::Empty {
+            generation: key.generation.wrapping_add(1),
+        };
+        match core::mem::replace(&mut self.slab[key.index], replacement) {
+            SlabSlot::Full { value, slot, .. } => {
+                self.free.push(key.index);
+                Some((value, slot))
+            }
+            SlabSlot::Empty { .. } => unreachable!("checked Full above"),
+        }
+    }
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        DelayQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_returns_pending_value() {
+        let mut queue: DelayQueue<i32> = DelayQueue::new();
+        let key = queue.insert(7, Duration::from_secs(10));
+        assert_eq!(queue.remove(&key), Some(7));
+        assert_eq!(queue.remove(&key), None);
+    }
+
+    #[test]
+    fn reset_keeps_slab_and_free_list_consistent() {
+        let mut queue: DelayQueue<i32> = DelayQueue::new();
+        let key = queue.insert(1, Duration::from_secs(10));
+
+        // Resetting must not leak the slot onto the free list: it stays `Full`
+        // and keeps its index out of `free`. Repeated resets used to push the
+        // index twice and make the next `insert` pop a "free" slot that was
+        // actually occupied, panicking at the `unreachable!`.
+        assert!(queue.reset(&key, Duration::from_secs(20)));
+        assert!(queue.reset(&key, Duration::from_secs(30)));
+
+        let other = queue.insert(2, Duration::from_secs(5));
+        assert_ne!(key.index, other.index);
+        assert_eq!(queue.remove(&key), Some(1));
+        assert_eq!(queue.remove(&other), Some(2));
+    }
+
+    #[test]
+    fn reset_on_stale_key_is_rejected() {
+        let mut queue: DelayQueue<i32> = DelayQueue::new();
+        let key = queue.insert(1, Duration::from_secs(10));
+        assert_eq!(queue.remove(&key), Some(1));
+        assert!(!queue.reset(&key, Duration::from_secs(5)));
+    }
+
+    #[cfg(feature = "mock-clock")]
+    #[test]
+    fn drains_in_deadline_order_after_advance() {
+        use async_std::task::block_on;
+        use futures::StreamExt;
+
+        super::super::clock::pause();
+        let mut queue: DelayQueue<&'static str> = DelayQueue::new();
+        queue.insert("late", Duration::from_secs(30));
+        let early = queue.insert("early", Duration::from_secs(10));
+        // Reschedule `early` even earlier to exercise `reset` on the drain path.
+        assert!(queue.reset(&early, Duration::from_secs(5)));
+
+        super::super::clock::advance(Duration::from_secs(30));
+        let first = block_on(queue.next());
+        let second = block_on(queue.next());
+        super::super::clock::resume();
+
+        assert_eq!(first, Some("early"));
+        assert_eq!(second, Some("late"));
+    }
+}
+
+impl<T: Unpin> Stream for DelayQueue<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        loop {
+            // Bound how many back-to-back expirations we hand out before
+            // yielding, so simultaneous deadlines don't starve the executor.
+            if this.yielded >= this.batch_limit {
+                this.yielded = 0;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let at = match this.heap.peek() {
+                Some(entry) => entry.at,
+                None => {
+                    // The queue is only transiently empty: a later `insert`
+                    // wakes `self.waker`, so park here instead of ending the
+                    // stream for a `while let Some(..) = q.next().await` loop.
+                    this.yielded = 0;
+                    this.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            };
+            let now = super::clock::now();
+            if at <= now {
+                this.yielded += 1;
+                let entry = this.heap.pop().expect("peeked above");
+                let replacement = SlabSlot::Empty {
+                    generation: match &this.slab[entry.key] {
+                        SlabSlot::Full { generation, .. } => generation.wrapping_add(1),
+                        SlabSlot::Empty { generation } => *generation,
+                    },
+                };
+                match core::mem::replace(&mut this.slab[entry.key], replacement) {
+                    SlabSlot::Full { value, .. } => {
+                        this.free.push(entry.key);
+                        return Poll::Ready(Some(value));
+                    }
+                    SlabSlot::Empty { .. } => unreachable!("heap entry without slab value"),
+                }
+            }
+
+            let dur = at.saturating_duration_since(now);
+            match &mut this.delay {
+                Some(delay) => delay.reset(dur),
+                None => this.delay = Some(Delay::new(dur)),
+            }
+            match Pin::new(this.delay.as_mut().unwrap()).poll(cx) {
+                Poll::Ready(()) => continue,
+                Poll::Pending => {
+                    this.yielded = 0;
+                    this.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
@@ -12,6 +12,7 @@
 use core::mem;
 use async_std::collections::Vec;
 
+#[derive(Debug)]
 pub struct Heap<T> {
     // Binary heap of items, plus the slab index indicating what position in the
     // list they're in.
@@ -23,11 +24,13 @@ pub struct Heap<T> {
     next_index: usize,
 }
 
+#[derive(Debug)]
 enum SlabSlot<T> {
     Empty { next: usize },
     Full { value: T },
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct Slot {
     idx: usize,
 }
@@ -0,0 +1,220 @@
+//! A `Delay` backed by a real timer driver.
+//!
+//! Rather than spinning the executor until the deadline is reached, every
+//! `Delay` registers its deadline with a process-wide driver. The driver keeps
+//! its pending timers in a hierarchical [`Wheel`] and runs on a helper thread
+//! that parks until the earliest deadline, wakes the expired tasks, and goes
+//! back to sleep.
+
+extern crate std;
+
+mod clock;
+mod delay_queue;
+// Retained as a fallback scheduling structure and reused by `DelayQueue`.
+mod heap;
+mod wheel;
+
+pub use self::delay_queue::{DelayQueue, Key};
+pub(crate) use self::clock::now;
+#[cfg(feature = "mock-clock")]
+pub use self::clock::{advance, pause, resume};
+
+use self::wheel::{Shared, State, Wheel};
+use async_std::time::Instant;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::vec::Vec;
+
+/// The process-wide timer driver.
+#[derive(Debug)]
+struct Driver {
+    inner: Mutex<Wheel>,
+    wakeup: Condvar,
+}
+
+/// Maximum number of timers the driver fires in a single pass before yielding
+/// the lock, so a thundering herd of simultaneous deadlines can't monopolise
+/// the driver thread.
+const BATCH_LIMIT: usize = 10;
+
+static DRIVER: OnceLock<Arc<Driver>> = OnceLock::new();
+
+/// Returns the global driver, spinning up its helper thread on first use.
+fn driver() -> &'static Arc<Driver> {
+    DRIVER.get_or_init(|| {
+        let driver = Arc::new(Driver {
+            inner: Mutex::new(Wheel::new()),
+            wakeup: Condvar::new(),
+        });
+        let handle = driver.clone();
+        thread::Builder::new()
+            .name("futures-timer".into())
+            .spawn(move || handle.run())
+            .expect("failed to spawn futures-timer driver thread");
+        driver
+    })
+}
+
+impl Driver {
+    /// The driver's event loop: park until the next deadline, fire everything
+    /// that has expired, repeat.
+    fn run(&self) {
+        let mut wheel = self.inner.lock().unwrap();
+        loop {
+            let now = wheel.now_tick();
+            let mut wakers = Vec::new();
+            let more = wheel.poll(now, BATCH_LIMIT, &mut wakers);
+
+            // Wake outside the lock so a woken task can re-register without
+            // contending with the driver.
+            if !wakers.is_empty() {
+                drop(wheel);
+                for waker in wakers {
+                    waker.wake();
+                }
+                wheel = self.inner.lock().unwrap();
+                continue;
+            }
+
+            // The batch cap left more timers already due; loop immediately
+            // rather than parking so they fire with minimal added latency.
+            if more {
+                continue;
+            }
+
+            wheel = match wheel.next_expiration() {
+                Some(tick) => match clock::park_until(wheel.instant_for(tick)) {
+                    Some(timeout) => self.wakeup.wait_timeout(wheel, timeout).unwrap().0,
+                    None => self.wakeup.wait(wheel).unwrap(),
+                },
+                None => self.wakeup.wait(wheel).unwrap(),
+            };
+        }
+    }
+}
+
+/// A future representing the notification that an elapsed duration has
+/// occurred.
+///
+/// This is created through the `Delay::new` method indicating when the future should fire.
+/// Note that these futures are not intended for high resolution timers, but rather they will
+/// likely fire some granularity after the exact instant that they're otherwise indicated to fire
+/// at.
+#[derive(Debug)]
+pub struct Delay {
+    at: Instant,
+    shared: Option<Arc<Mutex<Shared>>>,
+}
+
+impl Delay {
+    /// Creates a new future which will fire at `dur` time into the future.
+    ///
+    /// The returned object is registered with the process-wide timer driver,
+    /// which is spun up in a helper thread on first use.
+    ///
+    /// The deadline is anchored at construction, so a delay created while the
+    /// [`mock-clock`](crate) is paused fires once virtual time is advanced past
+    /// it even if it has not been polled yet.
+    #[inline]
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            at: clock::now() + duration,
+            shared: None,
+        }
+    }
+
+    /// Resets this timeout to a new timeout which will fire `duration` into
+    /// the future.
+    #[inline]
+    pub fn reset(&mut self, duration: Duration) {
+        self.cancel();
+        self.at = clock::now() + duration;
+    }
+
+    /// Marks this delay's wheel entry as cancelled if it has not yet fired; the
+    /// driver drops it lazily the next time its slot is processed.
+    fn cancel(&mut self) {
+        if let Some(shared) = self.shared.take() {
+            let mut shared = shared.lock().unwrap();
+            if shared.state == State::Pending {
+                shared.state = State::Cancelled;
+                shared.waker = None;
+            }
+        }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Some(shared) = &this.shared {
+            let mut shared = shared.lock().unwrap();
+            if shared.state == State::Fired {
+                return Poll::Ready(());
+            }
+            if shared
+                .waker
+                .as_ref()
+                .map(|w| !w.will_wake(cx.waker()))
+                .unwrap_or(true)
+            {
+                shared.waker = Some(cx.waker().clone());
+            }
+            return Poll::Pending;
+        }
+
+        // Already due (e.g. virtual time was advanced before the first poll):
+        // the wheel only schedules ticks after the ones it has processed, so
+        // complete here instead of registering.
+        if clock::now() >= this.at {
+            return Poll::Ready(());
+        }
+
+        // First poll: register the deadline with the driver.
+        let shared = Arc::new(Mutex::new(Shared {
+            waker: Some(cx.waker().clone()),
+            state: State::Pending,
+        }));
+        let at = this.at;
+        let driver = driver();
+        {
+            let mut wheel = driver.inner.lock().unwrap();
+            let when = wheel.tick_for(at);
+            wheel.insert(when, shared.clone());
+        }
+        driver.wakeup.notify_one();
+        this.shared = Some(shared);
+        Poll::Pending
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(all(test, feature = "mock-clock"))]
+mod tests {
+    use super::*;
+    use async_std::task::block_on;
+
+    #[test]
+    fn advance_fires_delay_without_waiting() {
+        clock::pause();
+        // A 30s delay must complete the instant virtual time jumps past it,
+        // even though no real time has elapsed. The deadline is anchored at
+        // construction, so advancing before the first poll still crosses it.
+        let delay = Delay::new(Duration::from_secs(30));
+        clock::advance(Duration::from_secs(30));
+        block_on(delay);
+        clock::resume();
+    }
+}
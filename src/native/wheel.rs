@@ -0,0 +1,315 @@
+//! A hierarchical timing wheel.
+//!
+//! The flat [`Heap`](super::heap::Heap) gives `O(log n)` insert and removal,
+//! which starts to hurt once thousands of timers churn every tick. This wheel
+//! trades that for amortized `O(1)` insert and poll.
+//!
+//! The wheel is split into [`NUM_LEVELS`] levels of [`SLOTS`] slots each. Level
+//! zero has the finest granularity — one tick (one millisecond) per slot, a
+//! `SLOTS`-tick span — and every higher level multiplies both the per-slot and
+//! the total span by `SLOTS`. An entry is dropped into the level whose span
+//! first contains `deadline - now`, computed from the number of significant
+//! bits of that distance, and a per-level `u64` bitmask of occupied slots lets
+//! [`Wheel::next_expiration`] find the soonest pending slot with a
+//! trailing-zeros scan. Advancing the clock processes whole slots at a time;
+//! when a slot on a higher level is reached its entries are *cascaded* down by
+//! re-inserting each into the now-nearer levels it has grown close enough to
+//! land in.
+
+extern crate std;
+
+use async_std::time::Instant;
+use core::task::Waker;
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
+
+/// Number of slots per level.
+const SLOTS: usize = 64;
+/// Number of bits addressed by a single level.
+const SLOT_BITS: u32 = 6;
+/// Number of levels in the wheel.
+pub(crate) const NUM_LEVELS: usize = 6;
+/// The largest duration, in ticks, the wheel can represent.
+const MAX_TICKS: u64 = 1 << (SLOT_BITS * NUM_LEVELS as u32);
+
+/// The shared state behind a single registered timer.
+///
+/// It is held both by the `Delay` and, through the wheel, by the driver, so
+/// either side can observe when the timer has fired and a cancelled timer can
+/// be dropped lazily the next time its slot is processed.
+#[derive(Debug)]
+pub(crate) struct Shared {
+    pub(crate) waker: Option<Waker>,
+    pub(crate) state: State,
+}
+
+/// The lifecycle of a registered timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum State {
+    /// Waiting in the wheel for its deadline.
+    Pending,
+    /// Fired by the driver.
+    Fired,
+    /// Cancelled by the owning `Delay`; the wheel will drop it when reached.
+    Cancelled,
+}
+
+#[derive(Debug)]
+struct Item {
+    /// Absolute deadline, in ticks since the wheel's `start`.
+    when: u64,
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[derive(Debug)]
+struct Level {
+    /// Bitmask of slots that currently hold at least one item.
+    occupied: u64,
+    slots: Vec<Vec<Item>>,
+}
+
+impl Level {
+    fn new() -> Level {
+        Level {
+            occupied: 0,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+        }
+    }
+}
+
+/// A hierarchical timing wheel keyed by absolute tick.
+#[derive(Debug)]
+pub(crate) struct Wheel {
+    /// The instant `tick` zero corresponds to.
+    start: Instant,
+    /// Ticks already processed.
+    elapsed: u64,
+    levels: Vec<Level>,
+}
+
+/// An expiration that is ready to be processed.
+struct Expiration {
+    level: usize,
+    slot: usize,
+    deadline: u64,
+}
+
+impl Wheel {
+    pub(crate) fn new() -> Wheel {
+        Wheel {
+            start: super::clock::now(),
+            elapsed: 0,
+            levels: (0..NUM_LEVELS).map(|_| Level::new()).collect(),
+        }
+    }
+
+    /// Converts an absolute instant into a tick relative to the wheel's start,
+    /// rounding up so a timer never fires early.
+    pub(crate) fn tick_for(&self, at: Instant) -> u64 {
+        let nanos = at.saturating_duration_since(self.start).as_nanos();
+        // One tick per millisecond, rounded up.
+        (nanos / 1_000_000 + u128::from(nanos % 1_000_000 != 0)) as u64
+    }
+
+    /// The tick the wheel currently considers "now".
+    pub(crate) fn now_tick(&self) -> u64 {
+        self.tick_for(super::clock::now())
+    }
+
+    /// Inserts a timer that should fire at `when` ticks. The caller keeps its
+    /// own clone of `shared` to later cancel or observe the timer.
+    pub(crate) fn insert(&mut self, when: u64, shared: Arc<Mutex<Shared>>) {
+        let when = when.max(self.elapsed + 1);
+        let (level, slot) = self.position(when);
+        self.levels[level].occupied |= 1 << slot;
+        self.levels[level].slots[slot].push(Item { when, shared });
+    }
+
+    /// The next tick at which some timer is due, if any.
+    pub(crate) fn next_expiration(&self) -> Option<u64> {
+        self.next_expiration_inner().map(|e| e.deadline)
+    }
+
+    /// The instant a given tick corresponds to.
+    pub(crate) fn instant_for(&self, tick: u64) -> Instant {
+        self.start + core::time::Duration::from_millis(tick)
+    }
+
+    /// Advances the wheel to `now`, collecting the wakers of expired timers
+    /// (cancelled timers are dropped, not returned).
+    ///
+    /// At most `limit` wakers are collected per call; when the cap is hit the
+    /// clock is *not* advanced past the unfinished slot, so a follow-up call
+    /// resumes exactly where this one stopped. Returns `true` if more timers
+    /// were already due but left unprocessed because of the cap.
+    pub(crate) fn poll(&mut self, now: u64, limit: usize, wakers: &mut Vec<Waker>) -> bool {
+        while let Some(expiration) = self.next_expiration_inner() {
+            if expiration.deadline > now {
+                break;
+            }
+            if wakers.len() >= limit {
+                return true;
+            }
+            self.elapsed = expiration.deadline;
+            if self.process(expiration, limit, wakers) {
+                return true;
+            }
+        }
+        // Nothing else is due before `now`; jump the clock forward.
+        self.elapsed = self.elapsed.max(now);
+        false
+    }
+
+    /// Level and slot a `when` tick maps to given the current `elapsed`.
+    fn position(&self, when: u64) -> (usize, usize) {
+        let level = self.level_for(when);
+        let slot = ((when >> (level as u32 * SLOT_BITS)) as usize) & (SLOTS - 1);
+        (level, slot)
+    }
+
+    /// The level whose span first contains `when - elapsed`.
+    fn level_for(&self, when: u64) -> usize {
+        let mut distance = when.saturating_sub(self.elapsed);
+        if distance >= MAX_TICKS {
+            distance = MAX_TICKS - 1;
+        }
+        // The most significant set bit picks the level.
+        let significant = 63u32.saturating_sub(distance.max(1).leading_zeros());
+        (significant / SLOT_BITS) as usize
+    }
+
+    fn next_expiration_inner(&self) -> Option<Expiration> {
+        for level in 0..NUM_LEVELS {
+            if let Some(expiration) = self.level_expiration(level) {
+                return Some(expiration);
+            }
+        }
+        None
+    }
+
+    fn level_expiration(&self, level: usize) -> Option<Expiration> {
+        let occupied = self.levels[level].occupied;
+        if occupied == 0 {
+            return None;
+        }
+        let slot_span = 1u64 << (level as u32 * SLOT_BITS);
+        let level_span = slot_span << SLOT_BITS;
+
+        let current_slot = (self.elapsed / slot_span) % SLOTS as u64;
+        let rotated = occupied.rotate_right(current_slot as u32);
+        let slot = ((current_slot + u64::from(rotated.trailing_zeros())) % SLOTS as u64) as usize;
+
+        let level_start = self.elapsed - (self.elapsed % level_span);
+        let mut deadline = level_start + slot as u64 * slot_span;
+        if deadline < self.elapsed {
+            deadline += level_span;
+        }
+        Some(Expiration {
+            level,
+            slot,
+            deadline,
+        })
+    }
+
+    /// Empties one slot: level-zero items fire, higher-level items cascade down.
+    ///
+    /// Stops early and returns `true` if the `limit` is reached mid-slot,
+    /// pushing the untouched items back so they fire on the next pass.
+    fn process(&mut self, expiration: Expiration, limit: usize, wakers: &mut Vec<Waker>) -> bool {
+        let items = core::mem::take(&mut self.levels[expiration.level].slots[expiration.slot]);
+        self.levels[expiration.level].occupied &= !(1 << expiration.slot);
+
+        let mut iter = items.into_iter();
+        for item in iter.by_ref() {
+            if expiration.level == 0 {
+                if wakers.len() >= limit {
+                    let mut remaining = std::vec![item];
+                    remaining.extend(iter);
+                    self.levels[expiration.level].occupied |= 1 << expiration.slot;
+                    self.levels[expiration.level].slots[expiration.slot] = remaining;
+                    // Re-process this slot next pass without advancing past it.
+                    self.elapsed = expiration.deadline.saturating_sub(1);
+                    return true;
+                }
+                let mut shared = item.shared.lock().unwrap();
+                if shared.state == State::Cancelled {
+                    continue;
+                }
+                shared.state = State::Fired;
+                if let Some(waker) = shared.waker.take() {
+                    wakers.push(waker);
+                }
+            } else {
+                // Cascade: the clock has advanced, so re-inserting drops the
+                // item into a now-nearer level.
+                self.insert(item.when, item.shared);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker;
+
+    fn pending() -> Arc<Mutex<Shared>> {
+        Arc::new(Mutex::new(Shared {
+            waker: Some(noop_waker()),
+            state: State::Pending,
+        }))
+    }
+
+    #[test]
+    fn insert_fires_on_level_zero() {
+        let mut wheel = Wheel::new();
+        let item = pending();
+        wheel.insert(1, item.clone());
+
+        let mut wakers = Vec::new();
+        assert!(!wheel.poll(1, 10, &mut wakers));
+        assert_eq!(wakers.len(), 1);
+        assert_eq!(item.lock().unwrap().state, State::Fired);
+    }
+
+    #[test]
+    fn distant_timers_cascade_before_firing() {
+        let mut wheel = Wheel::new();
+        let item = pending();
+        // A deadline past level zero's 64-tick span lands on a higher level.
+        wheel.insert(100, item.clone());
+        assert!(wheel.position(100).0 > 0);
+
+        // Advancing near the deadline cascades the item down a level but must
+        // not fire it early.
+        let mut wakers = Vec::new();
+        assert!(!wheel.poll(99, 10, &mut wakers));
+        assert!(wakers.is_empty());
+        assert_eq!(item.lock().unwrap().state, State::Pending);
+
+        // Crossing the deadline fires the cascaded entry.
+        assert!(!wheel.poll(100, 10, &mut wakers));
+        assert_eq!(wakers.len(), 1);
+        assert_eq!(item.lock().unwrap().state, State::Fired);
+    }
+
+    #[test]
+    fn batch_limit_leaves_remaining_timers_due() {
+        let mut wheel = Wheel::new();
+        let items: Vec<_> = (0..3).map(|_| pending()).collect();
+        for item in &items {
+            wheel.insert(1, item.clone());
+        }
+
+        let mut wakers = Vec::new();
+        // Cap of two fires two and reports that more are still due.
+        assert!(wheel.poll(1, 2, &mut wakers));
+        assert_eq!(wakers.len(), 2);
+
+        // A follow-up pass drains the rest.
+        wakers.clear();
+        assert!(!wheel.poll(1, 10, &mut wakers));
+        assert_eq!(wakers.len(), 1);
+    }
+}
@@ -0,0 +1,112 @@
+//! Wrapping futures with a deadline.
+
+use crate::Delay;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+/// Error returned by [`Timeout`] when the wrapped future does not complete
+/// before its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("future timed out")
+    }
+}
+
+/// An extension trait adding a deadline to any future.
+pub trait TimeoutExt: Future {
+    /// Requires the future to complete within `dur`, resolving to
+    /// `Err(Elapsed)` if it does not.
+    fn timeout(self, dur: Duration) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        Timeout {
+            future: self,
+            delay: Delay::new(dur),
+        }
+    }
+
+    /// Requires the future to complete within `dur`, calling `f` to produce the
+    /// output instead of erroring if it does not.
+    fn on_timeout<F>(self, dur: Duration, f: F) -> OnTimeout<Self, F>
+    where
+        Self: Sized,
+        F: FnOnce() -> Self::Output,
+    {
+        OnTimeout {
+            future: self,
+            delay: Delay::new(dur),
+            fallback: Some(f),
+        }
+    }
+}
+
+impl<F: Future> TimeoutExt for F {}
+
+/// A future that resolves to `Err(Elapsed)` if the inner future does not
+/// complete before the deadline. Created by [`TimeoutExt::timeout`].
+#[derive(Debug)]
+pub struct Timeout<F> {
+    future: F,
+    delay: Delay,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is structurally pinned and never moved out; `delay`
+        // is `Unpin` so projecting it by `&mut` is sound.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        if let Poll::Ready(output) = future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        match Pin::new(&mut this.delay).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A future that calls a fallback closure if the inner future does not complete
+/// before the deadline. Created by [`TimeoutExt::on_timeout`].
+#[derive(Debug)]
+pub struct OnTimeout<F, G> {
+    future: F,
+    delay: Delay,
+    fallback: Option<G>,
+}
+
+impl<F, G> Future for OnTimeout<F, G>
+where
+    F: Future,
+    G: FnOnce() -> F::Output,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: see `Timeout::poll`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        if let Poll::Ready(output) = future.poll(cx) {
+            return Poll::Ready(output);
+        }
+        match Pin::new(&mut this.delay).poll(cx) {
+            Poll::Ready(()) => {
+                let fallback = this
+                    .fallback
+                    .take()
+                    .expect("future polled after completion");
+                Poll::Ready(fallback())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}